@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+/// How the terminal cursor is drawn.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum TerminalCursorShape {
+    Block,
+    Underline,
+    Bar,
+}
+
+impl Default for TerminalCursorShape {
+    fn default() -> Self {
+        TerminalCursorShape::Block
+    }
+}
+
+/// Shell, environment, and rendering configuration for the integrated terminal.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct TerminalSettings {
+    pub shell: String,
+    pub shell_args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub scrollback_lines: usize,
+    /// Falls back to `Settings::buffer_font_family_name` when unset.
+    pub font_family: Option<String>,
+    pub font_size: Option<f32>,
+    pub cursor_shape: TerminalCursorShape,
+    pub cursor_blink: bool,
+}
+
+impl Default for TerminalSettings {
+    fn default() -> Self {
+        TerminalSettings {
+            shell: std::env::var("SHELL").unwrap_or_else(|_| "zsh".to_string()),
+            shell_args: Vec::new(),
+            env: HashMap::new(),
+            scrollback_lines: 10_000,
+            font_family: None,
+            font_size: None,
+            cursor_shape: TerminalCursorShape::default(),
+            cursor_blink: false,
+        }
+    }
+}
+
+pub struct Settings {
+    pub theme: Arc<theme::Theme>,
+    pub buffer_font_family_name: String,
+    pub buffer_font_size: f32,
+    pub terminal: TerminalSettings,
+}
@@ -0,0 +1,48 @@
+use alacritty_terminal::event::{Event, EventListener};
+use futures::{channel::mpsc, StreamExt};
+use gpui::ViewContext;
+
+use crate::TerminalView;
+
+/// The `EventListener` alacritty's `EventLoop` holds onto and calls from the
+/// PTY reader thread. Since that thread can't touch GPUI directly, `send_event`
+/// just coalesces `Wakeup`/`PtyWrite` notifications onto a channel; a task on
+/// the terminal view's context drains it and calls `cx.notify()` at most once
+/// per batch of PTY activity, so a flood of output doesn't trigger a relayout
+/// per byte.
+#[derive(Clone)]
+pub struct ZedTerminalHandle {
+    dirty_tx: mpsc::UnboundedSender<()>,
+}
+
+impl ZedTerminalHandle {
+    pub fn new(cx: &mut ViewContext<TerminalView>) -> ZedTerminalHandle {
+        let (dirty_tx, mut dirty_rx) = mpsc::unbounded();
+
+        cx.spawn_weak(|this, mut cx| async move {
+            while dirty_rx.next().await.is_some() {
+                // Drain any additional wakeups that piled up while we were
+                // waiting to be scheduled; one relayout covers all of them.
+                // `try_next()` returns `Ok(None)` once the channel is closed
+                // and drained, so stop there instead of spinning forever.
+                while let Ok(Some(_)) = dirty_rx.try_next() {}
+
+                match this.upgrade(&cx) {
+                    Some(this) => this.update(&mut cx, |_, cx| cx.notify()),
+                    None => break,
+                }
+            }
+        })
+        .detach();
+
+        ZedTerminalHandle { dirty_tx }
+    }
+}
+
+impl EventListener for ZedTerminalHandle {
+    fn send_event(&self, event: Event) {
+        if let Event::Wakeup | Event::PtyWrite(_) = event {
+            let _ = self.dirty_tx.unbounded_send(());
+        }
+    }
+}
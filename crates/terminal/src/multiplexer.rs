@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use gpui::{
+    actions, elements::*, impl_actions, AppContext, Entity, ModelHandle, MouseButton,
+    MutableAppContext, RenderContext, View, ViewContext, ViewHandle,
+};
+use project::{Project, ProjectEntryId, ProjectPath};
+use serde::Deserialize;
+use settings::Settings;
+use smallvec::SmallVec;
+use workspace::{Item, ItemNavHistory};
+
+use crate::TerminalView;
+
+actions!(
+    terminal,
+    [SplitHorizontal, SplitVertical, FocusNextPane, FocusPrevPane, ClosePane]
+);
+
+/// Grows the active pane's share of its split by this fraction (a negative
+/// value shrinks it), at its sibling's expense; each pane keeps at least 0.05.
+/// See `SplitTree::resize`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ResizePane(pub f32);
+
+/// Focuses the leaf pane with this id; dispatched by clicking a pane (see
+/// `TerminalPanel::render_tree`).
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct FocusPane(pub usize);
+
+impl_actions!(terminal, [ResizePane, FocusPane]);
+
+pub fn init(cx: &mut MutableAppContext) {
+    cx.add_action(TerminalPanel::split_horizontal);
+    cx.add_action(TerminalPanel::split_vertical);
+    cx.add_action(TerminalPanel::focus_next_pane);
+    cx.add_action(TerminalPanel::focus_prev_pane);
+    cx.add_action(TerminalPanel::close_active_pane);
+    cx.add_action(TerminalPanel::resize_active_pane);
+    cx.add_action(TerminalPanel::focus_pane);
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+struct PaneId(usize);
+
+#[derive(Clone, Copy)]
+enum SplitDirection {
+    Horizontal,
+    Vertical,
+}
+
+/// A binary layout tree of terminal panes. Each child of a `Split` carries the
+/// fraction of the split's space it occupies; a node's children's fractions
+/// always sum to 1.0.
+enum SplitTree {
+    Leaf(PaneId),
+    Split {
+        direction: SplitDirection,
+        children: Vec<(SplitTree, f32)>,
+    },
+}
+
+impl SplitTree {
+    fn leaves(&self) -> Vec<PaneId> {
+        match self {
+            SplitTree::Leaf(id) => vec![*id],
+            SplitTree::Split { children, .. } => children
+                .iter()
+                .flat_map(|(child, _)| child.leaves())
+                .collect(),
+        }
+    }
+
+    /// Replaces the `target` leaf with a new split containing `target` and
+    /// `new_pane`, divided evenly along `direction`. Returns `true` if
+    /// `target` was found.
+    fn split(&mut self, target: PaneId, new_pane: PaneId, direction: SplitDirection) -> bool {
+        match self {
+            SplitTree::Leaf(id) if *id == target => {
+                let old = std::mem::replace(self, SplitTree::Leaf(new_pane));
+                *self = SplitTree::Split {
+                    direction,
+                    children: vec![(old, 0.5), (SplitTree::Leaf(new_pane), 0.5)],
+                };
+                true
+            }
+            SplitTree::Leaf(_) => false,
+            SplitTree::Split { children, .. } => children
+                .iter_mut()
+                .any(|(child, _)| child.split(target, new_pane, direction)),
+        }
+    }
+
+    /// Removes the `target` leaf from the tree. A split left with a single
+    /// child collapses into that child.
+    fn remove(&mut self, target: PaneId) -> bool {
+        if let SplitTree::Split { children, .. } = self {
+            if let Some(index) = children
+                .iter()
+                .position(|(child, _)| matches!(child, SplitTree::Leaf(id) if *id == target))
+            {
+                children.remove(index);
+                if children.len() == 1 {
+                    *self = children.pop().unwrap().0;
+                }
+                return true;
+            }
+            return children.iter_mut().any(|(child, _)| child.remove(target));
+        }
+        false
+    }
+
+    /// Grows `target`'s share of its parent split by `delta`, taking the
+    /// space from its next sibling. Leaves ratios unchanged if `target` is
+    /// the tree's sole leaf.
+    fn resize(&mut self, target: PaneId, delta: f32) -> bool {
+        if let SplitTree::Split { children, .. } = self {
+            if let Some(index) = children
+                .iter()
+                .position(|(child, _)| matches!(child, SplitTree::Leaf(id) if *id == target))
+            {
+                if children.len() > 1 {
+                    let next = (index + 1) % children.len();
+                    let delta = delta.clamp(-children[index].1 + 0.05, children[next].1 - 0.05);
+                    children[index].1 += delta;
+                    children[next].1 -= delta;
+                }
+                return true;
+            }
+            return children.iter_mut().any(|(child, _)| child.resize(target, delta));
+        }
+        false
+    }
+}
+
+/// A single workspace item holding a tree of split terminal panes, each with
+/// its own independent PTY and `EventLoop` (see `ZedTerminal`). Mirrors how a
+/// terminal multiplexer tiles multiple shells behind one window.
+pub struct TerminalPanel {
+    working_directory: Option<PathBuf>,
+    panes: HashMap<PaneId, ViewHandle<TerminalView>>,
+    tree: SplitTree,
+    active_pane: PaneId,
+    next_pane_id: usize,
+}
+
+impl TerminalPanel {
+    pub fn new(working_directory: Option<PathBuf>, cx: &mut ViewContext<Self>) -> TerminalPanel {
+        let pane_id = PaneId(0);
+        let view = cx.add_view(|cx| TerminalView::new(cx, working_directory.clone()));
+
+        let mut panes = HashMap::new();
+        panes.insert(pane_id, view);
+
+        TerminalPanel {
+            working_directory,
+            panes,
+            tree: SplitTree::Leaf(pane_id),
+            active_pane: pane_id,
+            next_pane_id: 1,
+        }
+    }
+
+    fn add_pane(&mut self, direction: SplitDirection, cx: &mut ViewContext<Self>) {
+        let pane_id = PaneId(self.next_pane_id);
+        self.next_pane_id += 1;
+
+        let working_directory = self.working_directory.clone();
+        let view = cx.add_view(|cx| TerminalView::new(cx, working_directory));
+        self.panes.insert(pane_id, view);
+        self.tree.split(self.active_pane, pane_id, direction);
+        self.active_pane = pane_id;
+        cx.notify();
+    }
+
+    fn split_horizontal(&mut self, _: &SplitHorizontal, cx: &mut ViewContext<Self>) {
+        self.add_pane(SplitDirection::Horizontal, cx);
+    }
+
+    fn split_vertical(&mut self, _: &SplitVertical, cx: &mut ViewContext<Self>) {
+        self.add_pane(SplitDirection::Vertical, cx);
+    }
+
+    fn focus_next_pane(&mut self, _: &FocusNextPane, cx: &mut ViewContext<Self>) {
+        self.cycle_focus(1, cx);
+    }
+
+    fn focus_prev_pane(&mut self, _: &FocusPrevPane, cx: &mut ViewContext<Self>) {
+        self.cycle_focus(-1, cx);
+    }
+
+    fn cycle_focus(&mut self, step: isize, cx: &mut ViewContext<Self>) {
+        let leaves = self.tree.leaves();
+        let index = match leaves.iter().position(|id| *id == self.active_pane) {
+            Some(index) => index,
+            None => return,
+        };
+        let next = (index as isize + step).rem_euclid(leaves.len() as isize) as usize;
+        self.active_pane = leaves[next];
+        if let Some(view) = self.panes.get(&self.active_pane) {
+            cx.focus(view);
+        }
+        cx.notify();
+    }
+
+    fn focus_pane(&mut self, FocusPane(id): &FocusPane, cx: &mut ViewContext<Self>) {
+        let pane_id = PaneId(*id);
+        if let Some(view) = self.panes.get(&pane_id) {
+            self.active_pane = pane_id;
+            cx.focus(view);
+            cx.notify();
+        }
+    }
+
+    fn close_active_pane(&mut self, _: &ClosePane, cx: &mut ViewContext<Self>) {
+        // Closing the workspace item itself (when it's down to one pane) is
+        // the pane/tab's job, not this action's.
+        if self.panes.len() <= 1 {
+            return;
+        }
+
+        let closed = self.active_pane;
+        self.tree.remove(closed);
+        self.panes.remove(&closed);
+        if let Some(next) = self.tree.leaves().first().copied() {
+            self.active_pane = next;
+            if let Some(view) = self.panes.get(&next) {
+                cx.focus(view);
+            }
+        }
+        cx.notify();
+    }
+
+    fn resize_active_pane(&mut self, ResizePane(delta): &ResizePane, cx: &mut ViewContext<Self>) {
+        self.tree.resize(self.active_pane, *delta);
+        cx.notify();
+    }
+
+    fn render_tree(&self, tree: &SplitTree, cx: &mut RenderContext<Self>) -> ElementBox {
+        match tree {
+            SplitTree::Leaf(id) => match self.panes.get(id) {
+                Some(view) => {
+                    let pane_id = id.0;
+                    MouseEventHandler::<TerminalPanel>::new(pane_id, cx, |_, _| {
+                        ChildView::new(view).boxed()
+                    })
+                    .on_down(MouseButton::Left, move |_, cx| {
+                        cx.dispatch_action(FocusPane(pane_id));
+                    })
+                    .boxed()
+                }
+                None => Empty::new().boxed(),
+            },
+            SplitTree::Split { direction, children } => {
+                let mut flex = match direction {
+                    SplitDirection::Horizontal => Flex::row(),
+                    SplitDirection::Vertical => Flex::column(),
+                };
+                for (child, ratio) in children {
+                    flex = flex.with_child(self.render_tree(child, cx).flex(*ratio, true));
+                }
+                flex.boxed()
+            }
+        }
+    }
+}
+
+impl Entity for TerminalPanel {
+    type Event = ();
+}
+
+impl View for TerminalPanel {
+    fn ui_name() -> &'static str {
+        "TerminalPanel"
+    }
+
+    fn render(&mut self, cx: &mut RenderContext<'_, Self>) -> ElementBox {
+        self.render_tree(&self.tree, cx)
+    }
+}
+
+impl Item for TerminalPanel {
+    fn tab_content(&self, style: &theme::Tab, cx: &AppContext) -> ElementBox {
+        let search_theme = &cx.global::<Settings>().theme.search;
+        let title = self
+            .panes
+            .get(&self.active_pane)
+            .map(|view| view.read(cx).title())
+            .unwrap_or_else(|| "Terminal".to_string());
+
+        Flex::row()
+            .with_child(
+                Label::new(title, style.label.clone())
+                    .aligned()
+                    .contained()
+                    .with_margin_left(search_theme.tab_icon_spacing)
+                    .boxed(),
+            )
+            .boxed()
+    }
+
+    fn project_path(&self, _cx: &AppContext) -> Option<ProjectPath> {
+        None
+    }
+
+    fn project_entry_ids(&self, _cx: &AppContext) -> SmallVec<[ProjectEntryId; 3]> {
+        SmallVec::new()
+    }
+
+    fn is_singleton(&self, _cx: &AppContext) -> bool {
+        false
+    }
+
+    fn set_nav_history(&mut self, _: ItemNavHistory, _: &mut ViewContext<Self>) {}
+
+    fn can_save(&self, _cx: &AppContext) -> bool {
+        false
+    }
+
+    fn save(
+        &mut self,
+        _project: ModelHandle<Project>,
+        _cx: &mut ViewContext<Self>,
+    ) -> gpui::Task<gpui::anyhow::Result<()>> {
+        unreachable!("save should not have been called");
+    }
+
+    fn save_as(
+        &mut self,
+        _project: ModelHandle<Project>,
+        _abs_path: PathBuf,
+        _cx: &mut ViewContext<Self>,
+    ) -> gpui::Task<gpui::anyhow::Result<()>> {
+        unreachable!("save_as should not have been called");
+    }
+
+    fn reload(
+        &mut self,
+        _project: ModelHandle<Project>,
+        _cx: &mut ViewContext<Self>,
+    ) -> gpui::Task<gpui::anyhow::Result<()>> {
+        gpui::Task::ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_visits_in_split_order() {
+        let mut tree = SplitTree::Leaf(PaneId(0));
+        tree.split(PaneId(0), PaneId(1), SplitDirection::Horizontal);
+        tree.split(PaneId(1), PaneId(2), SplitDirection::Vertical);
+
+        assert_eq!(
+            tree.leaves(),
+            vec![PaneId(0), PaneId(1), PaneId(2)]
+        );
+    }
+
+    #[test]
+    fn split_replaces_leaf_with_even_split() {
+        let mut tree = SplitTree::Leaf(PaneId(0));
+        assert!(tree.split(PaneId(0), PaneId(1), SplitDirection::Horizontal));
+
+        match &tree {
+            SplitTree::Split { children, .. } => {
+                assert_eq!(children.len(), 2);
+                assert_eq!(children[0].1, 0.5);
+                assert_eq!(children[1].1, 0.5);
+            }
+            SplitTree::Leaf(_) => panic!("expected a split"),
+        }
+
+        // Splitting a pane that isn't in the tree is a no-op.
+        assert!(!tree.split(PaneId(99), PaneId(2), SplitDirection::Vertical));
+    }
+
+    #[test]
+    fn remove_collapses_single_child_split() {
+        let mut tree = SplitTree::Leaf(PaneId(0));
+        tree.split(PaneId(0), PaneId(1), SplitDirection::Horizontal);
+
+        assert!(tree.remove(PaneId(1)));
+        assert!(matches!(tree, SplitTree::Leaf(PaneId(0))));
+
+        // The sole remaining leaf can't be removed from inside a leaf node.
+        assert!(!tree.remove(PaneId(0)));
+    }
+
+    #[test]
+    fn remove_missing_pane_is_a_no_op() {
+        let mut tree = SplitTree::Leaf(PaneId(0));
+        tree.split(PaneId(0), PaneId(1), SplitDirection::Horizontal);
+
+        assert!(!tree.remove(PaneId(99)));
+        assert_eq!(tree.leaves(), vec![PaneId(0), PaneId(1)]);
+    }
+
+    #[test]
+    fn resize_shifts_ratio_between_siblings() {
+        let mut tree = SplitTree::Leaf(PaneId(0));
+        tree.split(PaneId(0), PaneId(1), SplitDirection::Horizontal);
+
+        assert!(tree.resize(PaneId(0), 0.2));
+        match &tree {
+            SplitTree::Split { children, .. } => {
+                assert!((children[0].1 - 0.7).abs() < f32::EPSILON);
+                assert!((children[1].1 - 0.3).abs() < f32::EPSILON);
+            }
+            SplitTree::Leaf(_) => panic!("expected a split"),
+        }
+    }
+
+    #[test]
+    fn resize_clamps_to_leave_each_sibling_a_sliver() {
+        let mut tree = SplitTree::Leaf(PaneId(0));
+        tree.split(PaneId(0), PaneId(1), SplitDirection::Horizontal);
+
+        assert!(tree.resize(PaneId(0), 10.0));
+        match &tree {
+            SplitTree::Split { children, .. } => {
+                assert!((children[0].1 - 0.95).abs() < f32::EPSILON);
+                assert!((children[1].1 - 0.05).abs() < f32::EPSILON);
+            }
+            SplitTree::Leaf(_) => panic!("expected a split"),
+        }
+    }
+
+    #[test]
+    fn resize_on_sole_leaf_is_a_no_op() {
+        let mut tree = SplitTree::Leaf(PaneId(0));
+        assert!(!tree.resize(PaneId(0), 0.5));
+        assert!(matches!(tree, SplitTree::Leaf(PaneId(0))));
+    }
+}
@@ -1,12 +1,15 @@
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use alacritty_terminal::{
-    config::{Config, Program, PtyConfig},
+    ansi::{Color as AnsiColor, NamedColor},
+    config::{Config, Program, PtyConfig, Scrolling},
     event::Notify,
     event_loop::{EventLoop, Msg, Notifier},
     grid::Dimensions,
     sync::FairMutex,
-    term::SizeInfo,
+    term::{cell::Flags, SizeInfo, TermMode},
     tty, Term,
 };
 use event_listener::ZedTerminalHandle;
@@ -14,39 +17,43 @@ use gpui::{
     actions,
     color::Color,
     elements::*,
-    fonts::{with_font_cache, TextStyle},
+    fonts::{with_font_cache, HighlightStyle, TextStyle, Weight},
     geometry::{rect::RectF, vector::vec2f},
+    keymap::Keystroke,
     text_layout::Line,
-    Entity, MutableAppContext, View, ViewContext,
+    Entity, Event, KeyDownEvent, MutableAppContext, View, ViewContext,
 };
 use mio_extras::channel::Sender;
-use project::{Project, ProjectPath};
-use settings::Settings;
-use smallvec::SmallVec;
+use project::Project;
+use settings::{Settings, TerminalCursorShape};
 use util::ResultExt;
-use workspace::{Item, Workspace};
+use workspace::Workspace;
 
 mod event_listener;
+mod multiplexer;
 
 //Action steps:
 //Create an action struct with actions!
 //Create an action handler that accepts that struct as an arg
 //Register that handler in `init`
 //If adding to key map file, reference the *struct name*, not the *handler function*
-actions!(terminal, [Deploy, FakeSendToPtyPlsDelete]);
+actions!(terminal, [Deploy]);
 
 pub fn init(cx: &mut MutableAppContext) {
     cx.add_action(ZedTerminal::deploy);
-    cx.add_action(ZedTerminal::fake_send_to_pty);
+    multiplexer::init(cx);
 }
 pub struct ZedTerminal {
     loop_tx: Notifier,
     term: Arc<FairMutex<Term<ZedTerminalHandle>>>,
     title: String,
+    last_size: Arc<Mutex<Option<SizeInfo>>>,
+    cursor_shape: TerminalCursorShape,
+    cursor_visible: Arc<Mutex<bool>>,
 }
 
 impl ZedTerminal {
-    fn new() -> ZedTerminal {
+    fn new(cx: &mut ViewContext<TerminalView>, working_directory: Option<PathBuf>) -> ZedTerminal {
         //Basic Alacritty terminal architecture:
         //- Need to create an alacritty_terminal::event::EventListener impl
         //  (so the terminal can control title & such)
@@ -64,17 +71,31 @@ impl ZedTerminal {
         //Not just a crappy terminal,
         //Full zed features like collaboration, multicursor, etc.
 
-        let zed_proxy = ZedTerminalHandle {};
+        let terminal_settings = cx.global::<Settings>().terminal.clone();
+
+        let zed_proxy = ZedTerminalHandle::new(cx);
+
+        let shell = match terminal_settings.shell_args.as_slice() {
+            [] => Program::Just(terminal_settings.shell.clone()),
+            args => Program::WithArgs {
+                program: terminal_settings.shell.clone(),
+                args: args.to_vec(),
+            },
+        };
 
         let pty_config = PtyConfig {
-            shell: Some(Program::Just("zsh".to_string())),
-            working_directory: None,
+            shell: Some(shell),
+            working_directory,
             hold: false,
         };
 
-        // TODO: Modify settings to populate the alacritty config
         let config = Config {
             pty_config: pty_config.clone(),
+            env: terminal_settings.env.clone(),
+            scrolling: Scrolling {
+                history: terminal_settings.scrollback_lines,
+                ..Default::default()
+            },
             ..Default::default()
         };
         let size_info = SizeInfo::new(100., 100., 5., 5., 0., 0., false);
@@ -97,10 +118,31 @@ impl ZedTerminal {
         let loop_tx = Notifier(event_loop.channel());
         let _io_thread = event_loop.spawn();
 
+        let cursor_visible = Arc::new(Mutex::new(true));
+        if terminal_settings.cursor_blink {
+            let cursor_visible = cursor_visible.clone();
+            cx.spawn_weak(|this, mut cx| async move {
+                loop {
+                    cx.background().timer(Duration::from_millis(500)).await;
+                    match this.upgrade(&cx) {
+                        Some(this) => {
+                            *cursor_visible.lock().unwrap() ^= true;
+                            this.update(&mut cx, |_, cx| cx.notify());
+                        }
+                        None => break,
+                    }
+                }
+            })
+            .detach();
+        }
+
         ZedTerminal {
             loop_tx,
             term,
             title: "Terminal".to_string(),
+            last_size: Arc::new(Mutex::new(Some(size_info))),
+            cursor_shape: terminal_settings.cursor_shape,
+            cursor_visible,
         }
     }
 
@@ -112,27 +154,47 @@ impl ZedTerminal {
             .update(cx, |project, cx| project.create_buffer("", None, cx))
             .log_err()
         {
-            let term = Arc::new(Mutex::new(ZedTerminal::new()));
-            cx.set_global(term.clone());
-            workspace.add_item(Box::new(cx.add_view(|_cx| TerminalView { term })), cx);
+            let working_directory = Self::project_root_path(workspace, &project, cx);
+            let view = cx.add_view(|cx| multiplexer::TerminalPanel::new(working_directory, cx));
+            workspace.add_item(Box::new(view), cx);
         }
     }
 
-    fn fake_send_to_pty(
-        workspace: &mut Workspace,
-        _: &FakeSendToPtyPlsDelete,
+    fn project_root_path(
+        workspace: &Workspace,
+        project: &gpui::ModelHandle<Project>,
         cx: &mut ViewContext<Workspace>,
-    ) {
-        let term = &cx.global::<Arc<Mutex<ZedTerminal>>>().lock().unwrap();
-        dbg!(&term.title);
-        term.loop_tx.notify("M".as_bytes());
+    ) -> Option<PathBuf> {
+        workspace
+            .active_item(cx)
+            .and_then(|item| item.project_path(cx))
+            .and_then(|project_path| {
+                project
+                    .read(cx)
+                    .worktree_for_id(project_path.worktree_id, cx)
+            })
+            .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
     }
 }
 
-struct TerminalView {
+pub(crate) struct TerminalView {
     term: Arc<Mutex<ZedTerminal>>,
 }
 
+impl TerminalView {
+    pub(crate) fn new(
+        cx: &mut ViewContext<TerminalView>,
+        working_directory: Option<PathBuf>,
+    ) -> TerminalView {
+        let term = Arc::new(Mutex::new(ZedTerminal::new(cx, working_directory)));
+        TerminalView { term }
+    }
+
+    pub(crate) fn title(&self) -> String {
+        self.term.lock().unwrap().title.clone()
+    }
+}
+
 impl Entity for TerminalView {
     type Event = ();
 }
@@ -144,27 +206,214 @@ impl View for TerminalView {
 
     fn render(&mut self, cx: &mut gpui::RenderContext<'_, Self>) -> ElementBox {
         let theme = cx.global::<Settings>().theme.clone();
-
-        TerminalEl::new(self.term.lock().unwrap().term.clone())
-            .contained()
-            // .with_style(theme.terminal.container)
-            .boxed()
+        let term = self.term.lock().unwrap();
+
+        TerminalEl::new(
+            term.term.clone(),
+            term.loop_tx.clone(),
+            term.last_size.clone(),
+            term.cursor_shape,
+            term.cursor_visible.clone(),
+        )
+        .contained()
+        // .with_style(theme.terminal.container)
+        .boxed()
     }
 }
 
 struct TerminalEl {
     grid_data: Arc<FairMutex<Term<ZedTerminalHandle>>>,
+    notifier: Notifier,
+    last_size: Arc<Mutex<Option<SizeInfo>>>,
+    cursor_shape: TerminalCursorShape,
+    cursor_visible: Arc<Mutex<bool>>,
 }
 
 impl TerminalEl {
-    fn new(term: Arc<FairMutex<Term<ZedTerminalHandle>>>) -> TerminalEl {
-        TerminalEl { grid_data: term }
+    fn new(
+        term: Arc<FairMutex<Term<ZedTerminalHandle>>>,
+        notifier: Notifier,
+        last_size: Arc<Mutex<Option<SizeInfo>>>,
+        cursor_shape: TerminalCursorShape,
+        cursor_visible: Arc<Mutex<bool>>,
+    ) -> TerminalEl {
+        TerminalEl {
+            grid_data: term,
+            notifier,
+            last_size,
+            cursor_shape,
+            cursor_visible,
+        }
+    }
+}
+
+/// Translates a keystroke into the byte sequence the PTY expects, honoring the
+/// terminal's application-cursor-keys mode for the arrow/Home/End family.
+/// Returns `None` for keystrokes the terminal doesn't have a mapping for
+/// (e.g. a bare modifier) or that it deliberately leaves alone (`cmd` chords),
+/// so the caller can let the event propagate to the workspace's keybindings
+/// instead of swallowing things like Cmd-W or Cmd-Shift-P as literal input.
+fn keystroke_to_pty_bytes(keystroke: &Keystroke, mode: TermMode) -> Option<Vec<u8>> {
+    // `cmd` is reserved for app-level keybindings; never treat it as terminal
+    // input so Cmd-chords reach the workspace instead of being typed into the
+    // shell.
+    if keystroke.cmd {
+        return None;
+    }
+
+    let key = keystroke.key.as_str();
+
+    // Ctrl-<letter> sends the corresponding C0 control code, e.g. Ctrl-C -> 0x03.
+    if keystroke.ctrl && !keystroke.alt {
+        if let Some(c) = key.chars().next().filter(|c| key.chars().count() == 1) {
+            if c.is_ascii_alphabetic() {
+                return Some(vec![c.to_ascii_uppercase() as u8 - b'A' + 1]);
+            }
+        }
     }
+
+    let app_cursor = mode.contains(TermMode::APP_CURSOR);
+    let bytes: &[u8] = match key {
+        "enter" => b"\r",
+        "backspace" => b"\x7f",
+        "tab" => b"\t",
+        "escape" => b"\x1b",
+        "up" if app_cursor => b"\x1bOA",
+        "up" => b"\x1b[A",
+        "down" if app_cursor => b"\x1bOB",
+        "down" => b"\x1b[B",
+        "right" if app_cursor => b"\x1bOC",
+        "right" => b"\x1b[C",
+        "left" if app_cursor => b"\x1bOD",
+        "left" => b"\x1b[D",
+        "home" if app_cursor => b"\x1bOH",
+        "home" => b"\x1b[H",
+        "end" if app_cursor => b"\x1bOF",
+        "end" => b"\x1b[F",
+        "pageup" => b"\x1b[5~",
+        "pagedown" => b"\x1b[6~",
+        "f1" => b"\x1bOP",
+        "f2" => b"\x1bOQ",
+        "f3" => b"\x1bOR",
+        "f4" => b"\x1bOS",
+        "f5" => b"\x1b[15~",
+        "f6" => b"\x1b[17~",
+        "f7" => b"\x1b[18~",
+        "f8" => b"\x1b[19~",
+        "f9" => b"\x1b[20~",
+        "f10" => b"\x1b[21~",
+        "f11" => b"\x1b[23~",
+        "f12" => b"\x1b[24~",
+        _ if key.chars().count() == 1 => {
+            // Alt-<char> sends the "meta" encoding most terminals use: an
+            // ESC prefix ahead of the character's own bytes.
+            let mut bytes = if keystroke.alt { vec![0x1b] } else { Vec::new() };
+            bytes.extend_from_slice(key.as_bytes());
+            return Some(bytes);
+        }
+        _ => return None,
+    };
+
+    Some(bytes.to_vec())
+}
+
+/// A run of same-background cells within a single grid row; `start_x` and
+/// `end_x` are pixel offsets from the start of the row's shaped line.
+struct BackgroundRun {
+    row: usize,
+    start_x: f32,
+    end_x: f32,
+    color: Color,
 }
 
 struct LayoutState {
     lines: Vec<Line>,
     line_height: f32,
+    background_color: Color,
+    background_runs: Vec<BackgroundRun>,
+    cursor: Option<BackgroundRun>,
+    cursor_shape: TerminalCursorShape,
+}
+
+/// Resolves an alacritty cell color against the theme's terminal palette.
+fn resolve_color(color: AnsiColor, palette: &theme::TerminalStyle) -> Color {
+    match color {
+        AnsiColor::Spec(rgb) => Color::new(rgb.r, rgb.g, rgb.b, 255),
+        AnsiColor::Named(named) => named_color(named, palette),
+        AnsiColor::Indexed(index) => indexed_color(index, palette),
+    }
+}
+
+fn named_color(named: NamedColor, palette: &theme::TerminalStyle) -> Color {
+    match named {
+        NamedColor::Black | NamedColor::DimBlack => palette.black,
+        NamedColor::Red | NamedColor::DimRed => palette.red,
+        NamedColor::Green | NamedColor::DimGreen => palette.green,
+        NamedColor::Yellow | NamedColor::DimYellow => palette.yellow,
+        NamedColor::Blue | NamedColor::DimBlue => palette.blue,
+        NamedColor::Magenta | NamedColor::DimMagenta => palette.magenta,
+        NamedColor::Cyan | NamedColor::DimCyan => palette.cyan,
+        NamedColor::White | NamedColor::DimWhite => palette.white,
+        NamedColor::BrightBlack => palette.bright_black,
+        NamedColor::BrightRed => palette.bright_red,
+        NamedColor::BrightGreen => palette.bright_green,
+        NamedColor::BrightYellow => palette.bright_yellow,
+        NamedColor::BrightBlue => palette.bright_blue,
+        NamedColor::BrightMagenta => palette.bright_magenta,
+        NamedColor::BrightCyan => palette.bright_cyan,
+        NamedColor::BrightWhite => palette.bright_white,
+        NamedColor::Foreground | NamedColor::BrightForeground => palette.foreground,
+        NamedColor::Background => palette.background,
+        NamedColor::Cursor => palette.foreground,
+        NamedColor::DimForeground => palette.foreground,
+    }
+}
+
+/// The 256-color xterm palette: 0-15 come from the theme, 16-231 are the 6x6x6
+/// color cube, and 232-255 are the grayscale ramp.
+fn indexed_color(index: u8, palette: &theme::TerminalStyle) -> Color {
+    match index {
+        0..=7 => named_color(
+            [
+                NamedColor::Black,
+                NamedColor::Red,
+                NamedColor::Green,
+                NamedColor::Yellow,
+                NamedColor::Blue,
+                NamedColor::Magenta,
+                NamedColor::Cyan,
+                NamedColor::White,
+            ][index as usize],
+            palette,
+        ),
+        8..=15 => named_color(
+            [
+                NamedColor::BrightBlack,
+                NamedColor::BrightRed,
+                NamedColor::BrightGreen,
+                NamedColor::BrightYellow,
+                NamedColor::BrightBlue,
+                NamedColor::BrightMagenta,
+                NamedColor::BrightCyan,
+                NamedColor::BrightWhite,
+            ][(index - 8) as usize],
+            palette,
+        ),
+        16..=231 => {
+            let i = index - 16;
+            let cube_component = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            Color::new(
+                cube_component(i / 36),
+                cube_component((i / 6) % 6),
+                cube_component(i % 6),
+                255,
+            )
+        }
+        232..=255 => {
+            let v = 8 + (index - 232) * 10;
+            Color::new(v, v, v, 255)
+        }
+    }
 }
 
 impl Element for TerminalEl {
@@ -176,37 +425,147 @@ impl Element for TerminalEl {
         constraint: gpui::SizeConstraint,
         cx: &mut gpui::LayoutContext,
     ) -> (gpui::geometry::vector::Vector2F, Self::LayoutState) {
-        let line = self
-            .grid_data
-            .lock()
-            .grid()
-            .display_iter()
-            .map(|c| c.c)
-            .collect::<String>();
-        dbg!(&line);
-        let chunks = vec![(&line[..], None)].into_iter();
+        let mut term = self.grid_data.lock();
+        let palette = cx.global::<Settings>().theme.terminal.clone();
+        let terminal_settings = &cx.global::<Settings>().terminal;
+        let font_size = terminal_settings
+            .font_size
+            .unwrap_or_else(|| cx.global::<Settings>().buffer_font_size);
+        let font_family_name = terminal_settings
+            .font_family
+            .clone()
+            .unwrap_or_else(|| cx.global::<Settings>().buffer_font_family_name.clone());
 
         let text_style = with_font_cache(cx.font_cache.clone(), || TextStyle {
-            color: Color::white(),
+            color: palette.foreground,
+            font_family_name: font_family_name.into(),
+            font_size,
             ..Default::default()
-        }); //Here it's 14?
+        });
+        let line_height = cx.font_cache.line_height(text_style.font_size);
+        let cell_width = cx.font_cache.em_width(text_style.font_id, text_style.font_size);
+
+        let size_info = SizeInfo::new(
+            constraint.max.x(),
+            constraint.max.y(),
+            cell_width,
+            line_height,
+            0.,
+            0.,
+            false,
+        );
+
+        let mut last_size = self.last_size.lock().unwrap();
+        if *last_size != Some(size_info) {
+            term.resize(size_info);
+            self.notifier.0.send(Msg::Resize(size_info)).log_err();
+            *last_size = Some(size_info);
+        }
+        drop(last_size);
+
+        let columns = term.grid().columns();
+
+        // Group consecutive cells that share a foreground color, weight and
+        // italic flag into chunks so `layout_highlighted_chunks` can shape
+        // each styled run once, and track
+        // consecutive same-background cells per row separately for the
+        // background fills `paint` draws before the glyphs.
+        let mut texts: Vec<(String, Option<HighlightStyle>)> = Vec::new();
+        let mut background_runs: Vec<(usize, usize, usize, Color)> = Vec::new(); // row, start_col, end_col, color
+        let mut column = 0;
+        let mut row = 0;
+
+        for cell in term.grid().display_iter() {
+            if column == columns {
+                texts.push(("\n".to_string(), None));
+                column = 0;
+                row += 1;
+            }
+
+            let inverse = cell.flags.contains(Flags::INVERSE);
+            let bold = cell.flags.contains(Flags::BOLD);
+            let italic = cell.flags.contains(Flags::ITALIC);
+            let (fg, bg) = if inverse { (cell.bg, cell.fg) } else { (cell.fg, cell.bg) };
+            let fg = resolve_color(fg, &palette);
+            let bg = resolve_color(bg, &palette);
+            let weight = if bold { Some(Weight::BOLD) } else { None };
+
+            match texts.last_mut() {
+                Some((text, Some(style)))
+                    if style.color == Some(fg)
+                        && style.weight == weight
+                        && style.italic == Some(italic) =>
+                {
+                    text.push(cell.c)
+                }
+                _ => texts.push((
+                    cell.c.to_string(),
+                    Some(HighlightStyle {
+                        color: Some(fg),
+                        weight,
+                        italic: Some(italic),
+                        ..Default::default()
+                    }),
+                )),
+            }
+
+            match background_runs.last_mut() {
+                Some((r, _, end, color)) if *r == row && *end == column && *color == bg => {
+                    *end = column + 1;
+                }
+                _ => background_runs.push((row, column, column + 1, bg)),
+            }
 
-        //Nescessary to send the
+            column += 1;
+        }
+
+        let line_count = row + 1;
+        let chunks = texts.iter().map(|(text, style)| (text.as_str(), *style));
         let shaped_lines = layout_highlighted_chunks(
             chunks,
             &text_style,
             cx.text_layout_cache,
             &cx.font_cache,
             usize::MAX,
-            line.matches('\n').count() + 1,
+            line_count,
         );
-        let line_height = cx.font_cache.line_height(text_style.font_size);
+
+        let background_runs = background_runs
+            .into_iter()
+            .filter_map(|(row, start_col, end_col, color)| {
+                let line = shaped_lines.get(row)?;
+                Some(BackgroundRun {
+                    row,
+                    start_x: line.x_for_index(start_col),
+                    end_x: line.x_for_index(end_col),
+                    color,
+                })
+            })
+            .collect();
+
+        let cursor_point = term.grid().cursor.point;
+        let cursor_row = cursor_point.line.0.max(0) as usize;
+        let cursor_col = cursor_point.column.0;
+        let cursor = if *self.cursor_visible.lock().unwrap() {
+            shaped_lines.get(cursor_row).map(|line| BackgroundRun {
+                row: cursor_row,
+                start_x: line.x_for_index(cursor_col),
+                end_x: line.x_for_index(cursor_col + 1),
+                color: palette.foreground,
+            })
+        } else {
+            None
+        };
 
         (
             constraint.max,
             LayoutState {
                 lines: shaped_lines,
                 line_height,
+                background_color: palette.background,
+                background_runs,
+                cursor,
+                cursor_shape: self.cursor_shape,
             },
         )
     }
@@ -218,12 +577,35 @@ impl Element for TerminalEl {
         layout: &mut Self::LayoutState,
         cx: &mut gpui::PaintContext,
     ) -> Self::PaintState {
-        let mut origin = bounds.origin();
-        dbg!(layout.line_height);
+        cx.scene.push_quad(gpui::scene::Quad {
+            bounds,
+            background: Some(layout.background_color),
+            border: Default::default(),
+            corner_radius: 0.,
+        });
+
+        let row_rect = |run: &BackgroundRun| {
+            RectF::new(
+                bounds.origin() + vec2f(run.start_x, run.row as f32 * layout.line_height),
+                vec2f(run.end_x - run.start_x, layout.line_height),
+            )
+        };
+
+        for run in &layout.background_runs {
+            let rect = row_rect(run);
+            if rect.intersects(visible_bounds) {
+                cx.scene.push_quad(gpui::scene::Quad {
+                    bounds: rect,
+                    background: Some(run.color),
+                    border: Default::default(),
+                    corner_radius: 0.,
+                });
+            }
+        }
 
+        let mut origin = bounds.origin();
         for line in &layout.lines {
             let boundaries = RectF::new(origin, vec2f(bounds.width(), layout.line_height));
-            dbg!(origin.y(), boundaries.max_y());
 
             if boundaries.intersects(visible_bounds) {
                 line.paint(origin, visible_bounds, layout.line_height, cx);
@@ -231,19 +613,53 @@ impl Element for TerminalEl {
 
             origin.set_y(boundaries.max_y());
         }
+
+        if let Some(cursor) = &layout.cursor {
+            let block = row_rect(cursor);
+            const STROKE_WIDTH: f32 = 2.;
+            let rect = match layout.cursor_shape {
+                TerminalCursorShape::Block => block,
+                TerminalCursorShape::Underline => RectF::new(
+                    block.origin() + vec2f(0., block.height() - STROKE_WIDTH),
+                    vec2f(block.width(), STROKE_WIDTH),
+                ),
+                TerminalCursorShape::Bar => {
+                    RectF::new(block.origin(), vec2f(STROKE_WIDTH, block.height()))
+                }
+            };
+            if rect.intersects(visible_bounds) {
+                cx.scene.push_quad(gpui::scene::Quad {
+                    bounds: rect,
+                    background: Some(cursor.color),
+                    border: Default::default(),
+                    corner_radius: 0.,
+                });
+            }
+        }
     }
 
     fn dispatch_event(
         &mut self,
-        _event: &gpui::Event,
+        event: &Event,
         _bounds: gpui::geometry::rect::RectF,
         _visible_bounds: gpui::geometry::rect::RectF,
         _layout: &mut Self::LayoutState,
         _paint: &mut Self::PaintState,
         _cx: &mut gpui::EventContext,
     ) -> bool {
-        false
-        // unreachable!("Should never be called hopefully")
+        match event {
+            Event::KeyDown(KeyDownEvent { keystroke, .. }) => {
+                let mode = *self.grid_data.lock().mode();
+                match keystroke_to_pty_bytes(keystroke, mode) {
+                    Some(bytes) => {
+                        self.notifier.notify(bytes);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
     }
 
     fn debug(
@@ -257,63 +673,134 @@ impl Element for TerminalEl {
     }
 }
 
-///Item is what workspace uses for deciding what to render in a pane
-///Often has a file path or somesuch
-impl Item for TerminalView {
-    fn tab_content(&self, style: &theme::Tab, cx: &gpui::AppContext) -> ElementBox {
-        let settings = cx.global::<Settings>();
-        let search_theme = &settings.theme.search;
-        Flex::row()
-            .with_child(
-                Label::new(self.term.lock().unwrap().title.clone(), style.label.clone())
-                    .aligned()
-                    .contained()
-                    .with_margin_left(search_theme.tab_icon_spacing)
-                    .boxed(),
-            )
-            .boxed()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_palette() -> theme::TerminalStyle {
+        theme::TerminalStyle {
+            black: Color::new(1, 0, 0, 255),
+            red: Color::new(2, 0, 0, 255),
+            green: Color::new(3, 0, 0, 255),
+            yellow: Color::new(4, 0, 0, 255),
+            blue: Color::new(5, 0, 0, 255),
+            magenta: Color::new(6, 0, 0, 255),
+            cyan: Color::new(7, 0, 0, 255),
+            white: Color::new(8, 0, 0, 255),
+            bright_black: Color::new(9, 0, 0, 255),
+            bright_red: Color::new(10, 0, 0, 255),
+            bright_green: Color::new(11, 0, 0, 255),
+            bright_yellow: Color::new(12, 0, 0, 255),
+            bright_blue: Color::new(13, 0, 0, 255),
+            bright_magenta: Color::new(14, 0, 0, 255),
+            bright_cyan: Color::new(15, 0, 0, 255),
+            bright_white: Color::new(16, 0, 0, 255),
+            foreground: Color::new(17, 0, 0, 255),
+            background: Color::new(18, 0, 0, 255),
+        }
     }
 
-    fn project_path(&self, _cx: &gpui::AppContext) -> Option<ProjectPath> {
-        None
+    #[test]
+    fn indexed_color_basic_16_matches_named() {
+        let palette = test_palette();
+        assert_eq!(indexed_color(0, &palette), palette.black);
+        assert_eq!(indexed_color(7, &palette), palette.white);
+        assert_eq!(indexed_color(8, &palette), palette.bright_black);
+        assert_eq!(indexed_color(15, &palette), palette.bright_white);
     }
 
-    fn project_entry_ids(&self, _cx: &gpui::AppContext) -> SmallVec<[project::ProjectEntryId; 3]> {
-        todo!()
+    #[test]
+    fn indexed_color_cube_boundaries() {
+        let palette = test_palette();
+        // Index 16 is the cube's (0, 0, 0) corner: pure black.
+        assert_eq!(indexed_color(16, &palette), Color::new(0, 0, 0, 255));
+        // Index 231 is the cube's (5, 5, 5) corner: pure white.
+        assert_eq!(indexed_color(231, &palette), Color::new(255, 255, 255, 255));
     }
 
-    fn is_singleton(&self, _cx: &gpui::AppContext) -> bool {
-        false
+    #[test]
+    fn indexed_color_grayscale_ramp_boundaries() {
+        let palette = test_palette();
+        assert_eq!(indexed_color(232, &palette), Color::new(8, 8, 8, 255));
+        assert_eq!(indexed_color(255, &palette), Color::new(238, 238, 238, 255));
     }
 
-    fn set_nav_history(&mut self, _: workspace::ItemNavHistory, _: &mut ViewContext<Self>) {}
+    #[test]
+    fn named_color_bright_foreground_uses_foreground() {
+        let palette = test_palette();
+        assert_eq!(
+            named_color(NamedColor::BrightForeground, &palette),
+            palette.foreground
+        );
+    }
 
-    fn can_save(&self, _cx: &gpui::AppContext) -> bool {
-        false
+    #[test]
+    fn resolve_color_spec_is_passed_through_as_rgb() {
+        let palette = test_palette();
+        let rgb = alacritty_terminal::ansi::Rgb {
+            r: 10,
+            g: 20,
+            b: 30,
+        };
+        assert_eq!(
+            resolve_color(AnsiColor::Spec(rgb), &palette),
+            Color::new(10, 20, 30, 255)
+        );
     }
 
-    fn save(
-        &mut self,
-        _project: gpui::ModelHandle<Project>,
-        _cx: &mut ViewContext<Self>,
-    ) -> gpui::Task<gpui::anyhow::Result<()>> {
-        unreachable!("save should not have been called");
+    fn keystroke(key: &str, ctrl: bool, alt: bool, cmd: bool) -> Keystroke {
+        Keystroke {
+            ctrl,
+            alt,
+            cmd,
+            shift: false,
+            function: false,
+            key: key.to_string(),
+        }
     }
 
-    fn save_as(
-        &mut self,
-        _project: gpui::ModelHandle<Project>,
-        _abs_path: std::path::PathBuf,
-        _cx: &mut ViewContext<Self>,
-    ) -> gpui::Task<gpui::anyhow::Result<()>> {
-        unreachable!("save_as should not have been called");
+    #[test]
+    fn ctrl_letter_sends_control_code() {
+        let bytes = keystroke_to_pty_bytes(&keystroke("c", true, false, false), TermMode::empty());
+        assert_eq!(bytes, Some(vec![0x03]));
     }
 
-    fn reload(
-        &mut self,
-        _project: gpui::ModelHandle<Project>,
-        _cx: &mut ViewContext<Self>,
-    ) -> gpui::Task<gpui::anyhow::Result<()>> {
-        gpui::Task::ready(Ok(()))
+    #[test]
+    fn cmd_chords_are_not_consumed() {
+        let bytes = keystroke_to_pty_bytes(&keystroke("w", false, false, true), TermMode::empty());
+        assert_eq!(bytes, None);
+
+        let bytes = keystroke_to_pty_bytes(&keystroke("c", true, false, true), TermMode::empty());
+        assert_eq!(bytes, None);
+    }
+
+    #[test]
+    fn printable_char_is_passed_through() {
+        let bytes = keystroke_to_pty_bytes(&keystroke("a", false, false, false), TermMode::empty());
+        assert_eq!(bytes, Some(b"a".to_vec()));
+    }
+
+    #[test]
+    fn alt_char_gets_meta_escape_prefix() {
+        let bytes = keystroke_to_pty_bytes(&keystroke("a", false, true, false), TermMode::empty());
+        assert_eq!(bytes, Some(vec![0x1b, b'a']));
+    }
+
+    #[test]
+    fn arrow_keys_respect_app_cursor_mode() {
+        let normal = keystroke_to_pty_bytes(&keystroke("up", false, false, false), TermMode::empty());
+        assert_eq!(normal, Some(b"\x1b[A".to_vec()));
+
+        let app_cursor = keystroke_to_pty_bytes(
+            &keystroke("up", false, false, false),
+            TermMode::APP_CURSOR,
+        );
+        assert_eq!(app_cursor, Some(b"\x1bOA".to_vec()));
+    }
+
+    #[test]
+    fn unmapped_keystroke_propagates() {
+        let bytes = keystroke_to_pty_bytes(&keystroke("f20", false, false, false), TermMode::empty());
+        assert_eq!(bytes, None);
     }
 }